@@ -11,8 +11,10 @@ use std::{
 };
 
 use flexbuffers::FlexbufferSerializer;
-use napi::bindgen_prelude::{Buffer, Uint8Array};
+use napi::bindgen_prelude::{BigInt, Buffer, Uint8Array};
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
 use napi_derive::napi;
+use rayon::prelude::*;
 use serde::Serialize;
 use static_assertions::const_assert;
 
@@ -155,6 +157,89 @@ pub fn parse_sync_buffer(source_text: String, options: Option<ParserOptions>) ->
     serializer.take_buffer().into()
 }
 
+/// One file to parse as part of a [`parse_batch`] (or [`parse_batch_streaming`]) call.
+#[napi(object)]
+pub struct ParseFileInput {
+    pub filename: String,
+    pub source_text: String,
+    pub options: Option<ParserOptions>,
+}
+
+/// Parse a batch of files in parallel, on a rayon thread pool.
+///
+/// Each file gets its own `Allocator`, so files are fully independent of one another.
+/// Results are collected back in the same order as `files`, regardless of which order
+/// they finish parsing in.
+///
+/// This amortizes the per-call N-API overhead of [`parse_sync`] across a whole batch,
+/// which matters when a caller (e.g. a bundler) wants to parse a whole project at once.
+///
+/// # Panics
+///
+/// * File extension is invalid
+/// * Serde JSON serialization
+#[napi]
+pub fn parse_batch(files: Vec<ParseFileInput>) -> Vec<ParseResult> {
+    files
+        .into_par_iter()
+        .map(|file| {
+            let ParseFileInput { filename, source_text, options } = file;
+            let mut options = options.unwrap_or_default();
+            if options.source_filename.is_none() {
+                options.source_filename = Some(filename);
+            }
+            parse_sync(source_text, Some(options))
+        })
+        .collect()
+}
+
+/// One completed file's result from a [`parse_batch_streaming`] call.
+#[napi(object)]
+pub struct ParseBatchStreamResult {
+    pub index: u32,
+    pub program: String,
+    pub comments: Vec<Comment>,
+    pub errors: Vec<String>,
+}
+
+/// Parse a batch of files on a worker pool, invoking `callback` once per file as soon as
+/// it completes, rather than waiting for the whole batch to finish like [`parse_batch`] does.
+///
+/// `index` in the callback's result matches the file's position in `files`, so callers can
+/// reassemble results in order (or just use them as they stream in for progress reporting).
+///
+/// This function returns immediately; the batch is parsed on a background thread (itself
+/// fanning out over rayon), so the JS event loop is never blocked on the slowest file.
+///
+/// # Panics
+///
+/// * File extension is invalid
+/// * Serde JSON serialization
+#[napi(
+    ts_args_type = "files: Array<ParseFileInput>, callback: (result: ParseBatchStreamResult) => void"
+)]
+pub fn parse_batch_streaming(
+    files: Vec<ParseFileInput>,
+    callback: ThreadsafeFunction<ParseBatchStreamResult, ErrorStrategy::Fatal>,
+) {
+    // Hand the batch off to a background thread and return immediately, so this call doesn't
+    // block the JS event loop until the slowest file in `files` finishes parsing.
+    std::thread::spawn(move || {
+        files.into_par_iter().enumerate().for_each(|(index, file)| {
+            let ParseFileInput { filename, source_text, options } = file;
+            let mut options = options.unwrap_or_default();
+            if options.source_filename.is_none() {
+                options.source_filename = Some(filename);
+            }
+            let ParseResult { program, comments, errors } =
+                parse_sync(source_text, Some(options));
+            #[allow(clippy::cast_possible_truncation)]
+            let result = ParseBatchStreamResult { index: index as u32, program, comments, errors };
+            callback.call(result, ThreadsafeFunctionCallMode::NonBlocking);
+        });
+    });
+}
+
 /// Returns schema for AST types
 ///
 /// # Panics
@@ -165,28 +250,57 @@ pub fn get_schema() -> String {
     serde_json::to_string(&types).unwrap()
 }
 
-const RAW_BUFFER_SIZE: usize = 1 << 31; // 2 GiB
-const RAW_BUFFER_ALIGN: usize = 1 << 32; // 4 GiB
+// Default buffer size: 2 GiB. Callers parsing larger sources can request a bigger buffer
+// from `create_buffer`, up to `MAX_RAW_BUFFER_SIZE`.
+const DEFAULT_RAW_BUFFER_SIZE: u64 = 1 << 31; // 2 GiB
+const RAW_BUFFER_ALIGN: u64 = 1 << 32; // 4 GiB
+// `offset = ptr & (RAW_BUFFER_ALIGN - 1)` only recovers a valid buffer-relative offset when
+// the whole buffer sits inside a single aligned `RAW_BUFFER_ALIGN`-sized region - a bigger
+// buffer would alias offsets between its 4 GiB segments. So the buffer can never be larger
+// than its alignment.
+const MAX_RAW_BUFFER_SIZE: u64 = RAW_BUFFER_ALIGN; // 4 GiB
+const METADATA_SIZE: u64 = 16;
 const ALLOC_ATTEMPTS: usize = 10;
 
+const_assert!(DEFAULT_RAW_BUFFER_SIZE.is_power_of_two());
+const_assert!(MAX_RAW_BUFFER_SIZE.is_power_of_two());
+const_assert!(RAW_BUFFER_ALIGN.is_power_of_two());
+const_assert!(MAX_RAW_BUFFER_SIZE <= RAW_BUFFER_ALIGN);
+const_assert!(MAX_RAW_BUFFER_SIZE <= isize::MAX as u64);
+
 /// Create a buffer for use with `parse_sync_raw`.
+///
+/// `size` defaults to 2 GiB. Pass a larger `BigInt` (a power of two, no larger than
+/// `RAW_BUFFER_ALIGN`, i.e. 4 GiB) to parse sources that don't fit in the default buffer.
+///
 /// # Panics
-/// Panics if cannot allocate buffer.
+/// Panics if `size` does not fit losslessly in a `u64`, is not a power of two, is larger
+/// than the maximum, or the buffer cannot be allocated.
 #[napi]
-pub fn create_buffer() -> Uint8Array {
+pub fn create_buffer(size: Option<BigInt>) -> Uint8Array {
     // 32-bit systems are not supported
     const_assert!(std::mem::size_of::<usize>() >= 8);
 
+    let size = size.map_or(DEFAULT_RAW_BUFFER_SIZE, |size| {
+        let (lossless, size) = size.get_u64();
+        assert!(lossless, "buffer size must be a non-negative integer that fits in a u64");
+        size
+    });
+    assert!(size.is_power_of_two(), "buffer size must be a power of two");
+    assert!(size <= MAX_RAW_BUFFER_SIZE, "buffer size exceeds maximum");
+    #[allow(clippy::cast_possible_truncation)]
+    let size = size as usize;
+
     // Attempt to create allocation with required alignment
-    let mut align = RAW_BUFFER_ALIGN;
-    let layout = Layout::from_size_align(RAW_BUFFER_SIZE, align).unwrap();
+    let mut align = RAW_BUFFER_ALIGN as usize;
+    let layout = Layout::from_size_align(size, align).unwrap();
     // SAFETY: Layout was created safely
     let mut data_ptr = unsafe { alloc::alloc(layout) };
     if data_ptr.is_null() {
         // Could not allocate with this alignment.
         // Try again with lower alignment until get alignment we need.
         align /= 2;
-        let less_aligned_layout = Layout::from_size_align(RAW_BUFFER_SIZE, align).unwrap();
+        let less_aligned_layout = Layout::from_size_align(size, align).unwrap();
 
         let mut rejected_alloc_ptrs = Vec::with_capacity(ALLOC_ATTEMPTS);
         for _ in 0..ALLOC_ATTEMPTS {
@@ -196,7 +310,7 @@ pub fn create_buffer() -> Uint8Array {
                 break;
             }
 
-            if try_data_ptr as usize % RAW_BUFFER_ALIGN == 0 {
+            if try_data_ptr as usize % RAW_BUFFER_ALIGN as usize == 0 {
                 data_ptr = try_data_ptr;
                 break;
             }
@@ -214,12 +328,12 @@ pub fn create_buffer() -> Uint8Array {
     }
 
     // Return as NAPI `Uint8Array`, borrowing the allocation's memory.
-    // SAFETY: `data_ptr` is valid for reading `FOUR_GIB` bytes.
+    // SAFETY: `data_ptr` is valid for reading `size` bytes.
     // TODO: Add comment pointing to Github discussion where NodeJS maintainer said
     // passing uninitialized data is fine
     unsafe {
-        Uint8Array::with_external_data(data_ptr, RAW_BUFFER_SIZE, move |ptr, _len| {
-            let layout = Layout::from_size_align(RAW_BUFFER_SIZE, align).unwrap();
+        Uint8Array::with_external_data(data_ptr, size, move |ptr, _len| {
+            let layout = Layout::from_size_align(size, align).unwrap();
             alloc::dealloc(ptr, layout);
         })
     }
@@ -227,12 +341,15 @@ pub fn create_buffer() -> Uint8Array {
 
 /// Returns AST as raw bytes from Rust's memory.
 ///
-/// Caller provides a buffer.
+/// Caller provides a buffer (as created by `create_buffer`).
 /// Source text must be written into the start of the buffer, and its length provided as `source_len`.
 /// This function will parse the source, and write the AST into the buffer, starting at the end.
 /// It also writes to the buffer after the source text:
-/// * Offset of `Program` in the buffer.
-/// * Mask for converting 64-bit pointers to buffer offsets.
+/// * Offset of `Program` in the buffer, as a 64-bit value.
+/// * Mask for converting 64-bit pointers to buffer offsets, as a 64-bit value.
+///
+/// Metadata is written as 64-bit values (exchanged with JS as `BigInt`) so that buffers
+/// larger than `u32::MAX` bytes round-trip their offsets without losing precision.
 ///
 /// # Panics
 /// Panics if AST takes more memory than expected.
@@ -245,22 +362,25 @@ pub fn parse_sync_raw(mut buff: Uint8Array, source_len: u32, options: Option<Par
     // Check buffer has expected size and alignment
     let buff = &mut *buff;
     let buff_ptr = (buff as *mut [u8]).cast::<u8>();
-    assert_eq!(buff.len(), RAW_BUFFER_SIZE);
-    assert_eq!(buff_ptr as usize % RAW_BUFFER_ALIGN, 0);
+    let buff_len = buff.len() as u64;
+    assert!(buff_len.is_power_of_two(), "buffer size must be a power of two");
+    assert!(buff_len <= RAW_BUFFER_ALIGN, "buffer size exceeds maximum");
+    assert_eq!(buff_ptr as u64 % RAW_BUFFER_ALIGN, 0);
 
     // TODO: Need fallback for when could not create buffer with required alignment
 
     // Get offsets and size of data region to be managed by allocator.
-    // Leave space for source before it, and 16 bytes for metadata after it.
-    const METADATA_SIZE: usize = 16;
-    let data_offset = (source_len as usize).next_multiple_of(16);
-    let data_size = RAW_BUFFER_SIZE.saturating_sub(data_offset + METADATA_SIZE);
+    // Leave space for source before it, and `METADATA_SIZE` bytes for metadata after it.
+    let data_offset = (source_len as u64).next_multiple_of(16);
+    let data_size = buff_len.saturating_sub(data_offset + METADATA_SIZE);
+    #[allow(clippy::cast_possible_truncation)]
+    let data_size = data_size as usize;
     assert!(data_size >= Allocator::MIN_SIZE);
 
     // Create `Allocator`.
     // Wrap in `ManuallyDrop` so the allocation doesn't get freed at end of function, or if panic.
     // SAFETY: `data_offset` is less than `buff.len()`
-    let data_ptr = unsafe { buff_ptr.add(data_offset) };
+    let data_ptr = unsafe { buff_ptr.add(data_offset as usize) };
     // SAFETY: `data_ptr` and `data_size` are multiples of 16.
     // `data_size` is greater than `Allocator::MIN_SIZE`.
     // `data_ptr + data_size` is not after end of `buff`.
@@ -278,14 +398,61 @@ pub fn parse_sync_raw(mut buff: Uint8Array, source_len: u32, options: Option<Par
         (program as *const Program).cast::<u8>()
     };
 
-    // Write offset of program into end of buffer
-    #[allow(clippy::cast_possible_truncation)]
-    let program_offset = program_ptr as u32;
-    const METADATA_OFFSET: usize = RAW_BUFFER_SIZE - METADATA_SIZE;
-    // SAFETY: `METADATA_OFFSET` is less than length of `buff`
+    // Write offset of program, and the mask for converting pointers within the buffer to
+    // buffer-relative offsets, into end of buffer.
+    //
+    // `create_buffer` aligns the allocation to `RAW_BUFFER_ALIGN`, so every pointer the
+    // allocator hands out (they all point within the buffer) satisfies
+    // `offset = ptr & (RAW_BUFFER_ALIGN - 1)`. Writing the mask alongside the program offset
+    // means JS can turn any embedded pointer field into a buffer offset without a relocation
+    // pass over the AST.
+    const PTR_MASK: u64 = RAW_BUFFER_ALIGN - 1;
+    // `program_ptr` is an absolute address - mask it down to a buffer-relative offset before
+    // writing it out, same as every other pointer field the JS reader resolves via `ptrMask`.
+    let program_offset = (program_ptr as u64) & PTR_MASK;
+    let metadata_offset = buff_len - METADATA_SIZE;
+    const_assert!(METADATA_SIZE >= 16);
+    // SAFETY: `metadata_offset` is less than length of `buff`
     #[allow(clippy::cast_ptr_alignment)]
     unsafe {
-        buff_ptr.add(METADATA_OFFSET).cast::<u32>().write(program_offset);
+        buff_ptr.add(metadata_offset as usize).cast::<u64>().write(program_offset);
+        buff_ptr.add(metadata_offset as usize + 8).cast::<u64>().write(PTR_MASK);
+    }
+}
+
+/// Metadata written to the end of the buffer by [`parse_sync_raw`].
+#[napi(object)]
+pub struct RawTransferMetadata {
+    /// Offset of `Program` within the buffer, as a `BigInt` so offsets above `u32::MAX`
+    /// round-trip correctly.
+    #[napi(ts_type = "bigint")]
+    pub program_offset: BigInt,
+    /// Mask to apply to a pointer (read from the buffer) to turn it into a buffer offset,
+    /// as a `BigInt`.
+    #[napi(ts_type = "bigint")]
+    pub ptr_mask: BigInt,
+}
+
+/// Read the [`RawTransferMetadata`] that [`parse_sync_raw`] wrote into `buff`.
+///
+/// JS uses this together with `get_schema`'s layout to lazily deserialize nodes out of the
+/// buffer: walk the schema, and for each field typed as a pointer, read its 64-bit value and
+/// apply `ptr_mask` to get the buffer offset the field points to.
+#[napi]
+#[allow(clippy::needless_pass_by_value, clippy::cast_ptr_alignment)]
+pub fn read_raw_transfer_metadata(buff: Uint8Array) -> RawTransferMetadata {
+    let buff_len = buff.len() as u64;
+    let metadata_offset = buff_len - METADATA_SIZE;
+    let buff_ptr = (&*buff as *const [u8]).cast::<u8>();
+    // SAFETY: `metadata_offset + 16` is in bounds of `buff`.
+    // `parse_sync_raw` wrote valid `u64`s at these offsets before returning.
+    unsafe {
+        let program_offset = buff_ptr.add(metadata_offset as usize).cast::<u64>().read();
+        let ptr_mask = buff_ptr.add(metadata_offset as usize + 8).cast::<u64>().read();
+        RawTransferMetadata {
+            program_offset: BigInt::from(program_offset),
+            ptr_mask: BigInt::from(ptr_mask),
+        }
     }
 }
 
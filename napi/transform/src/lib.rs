@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use base64::Engine;
+use napi_derive::napi;
+use serde::Serialize;
+
+use oxc_allocator::Allocator;
+use oxc_codegen::{CodeGenerator, CodegenOptions, CodegenSourceMapOptions};
+use oxc_diagnostics::miette::NamedSource;
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+use oxc_transformer::{React, ReactJsxRuntime, ReactOptions, Transformation};
+
+/// Options for [`transform_sync`].
+#[napi(object)]
+#[derive(Default)]
+pub struct TransformOptions {
+    #[napi(ts_type = "'script' | 'module' | 'unambiguous' | undefined")]
+    pub source_type: Option<String>,
+
+    #[napi(ts_type = "'classic' | 'automatic' | undefined")]
+    pub jsx_runtime: Option<String>,
+
+    /// Generate a source map alongside the transformed code.
+    ///
+    /// Default: `false`.
+    pub sourcemap: Option<bool>,
+
+    /// Embed the source map as a `//# sourceMappingURL=` data URL in `code`, instead of
+    /// returning it separately as `map`.
+    ///
+    /// Default: `false`.
+    pub inline_sourcemap: Option<bool>,
+
+    /// Include the original source text in the source map's `sourcesContent`.
+    ///
+    /// Default: `true`.
+    pub sources_content: Option<bool>,
+}
+
+/// A Source Map v3 payload.
+///
+/// <https://tc39.es/source-map/>
+#[napi(object)]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMap {
+    pub version: u8,
+    pub sources: Vec<String>,
+    pub sources_content: Option<Vec<String>>,
+    pub names: Vec<String>,
+    pub mappings: String,
+}
+
+#[napi(object)]
+pub struct TransformResult {
+    pub code: String,
+    pub map: Option<SourceMap>,
+    pub errors: Vec<String>,
+}
+
+/// Parse `source_text` and print it back to JavaScript via codegen, optionally alongside a
+/// source map.
+///
+/// `React`'s JSX-lowering plugins aren't wired in yet (see
+/// `crates/oxc_transformer/src/react/mod.rs` - `React::new` always constructs an empty
+/// `plugins` list). Rather than silently emitting untransformed JSX back to the caller as if
+/// it were runnable JS, `transform_sync` refuses JSX/TSX input (reported via `errors`) until
+/// that's implemented.
+///
+/// # Panics
+///
+/// * File extension is invalid
+#[allow(clippy::needless_pass_by_value)]
+#[napi]
+pub fn transform_sync(
+    filename: String,
+    source_text: String,
+    options: Option<TransformOptions>,
+) -> TransformResult {
+    let options = options.unwrap_or_default();
+
+    let source_type = SourceType::from_path(&filename).unwrap();
+    let source_type = match options.source_type.as_deref() {
+        Some("script") => source_type.with_script(true),
+        Some("module") => source_type.with_module(true),
+        _ => source_type,
+    };
+
+    if source_type.is_jsx() {
+        let error = format!(
+            "transform_sync: JSX lowering is not implemented yet (`React` has no plugins \
+             wired in), so `{filename}` cannot be transformed"
+        );
+        return TransformResult { code: String::new(), map: None, errors: vec![error] };
+    }
+
+    let allocator = Allocator::default();
+    let parser_ret = Parser::new(&allocator, &source_text, source_type).parse();
+
+    let mut program = parser_ret.program;
+
+    let react_options = ReactOptions {
+        runtime: match options.jsx_runtime.as_deref() {
+            Some("classic") => ReactJsxRuntime::Classic,
+            _ => ReactJsxRuntime::Automatic,
+        },
+        ..ReactOptions::default()
+    };
+    // No-op today (`React`'s plugin list is always empty) - harmless here since the JSX guard
+    // above already refuses the only input this would need to rewrite.
+    React::new(react_options, Default::default()).transform(&mut program);
+
+    let want_map = options.sourcemap.unwrap_or(false) || options.inline_sourcemap.unwrap_or(false);
+    let sources_content = options.sources_content.unwrap_or(true);
+
+    let codegen_options = CodegenOptions {
+        source_map: want_map.then(|| CodegenSourceMapOptions {
+            filename: filename.clone(),
+            sources_content,
+        }),
+        ..CodegenOptions::default()
+    };
+    let codegen_ret = CodeGenerator::new().with_options(codegen_options).build(&program);
+
+    let mut code = codegen_ret.source_text;
+    let map = codegen_ret.source_map.map(|source_map| {
+        let json = source_map.to_json();
+        SourceMap {
+            version: json.version,
+            sources: json.sources,
+            sources_content: json.sources_content,
+            names: json.names,
+            mappings: json.mappings,
+        }
+    });
+
+    // When inlining, the map travels as a `//# sourceMappingURL=` comment in `code` instead
+    // of as a separate `map` field.
+    let map = if options.inline_sourcemap.unwrap_or(false) {
+        if let Some(source_map) = &map {
+            let json = serde_json::to_string(source_map).unwrap();
+            let data_url = base64::engine::general_purpose::STANDARD.encode(json.as_bytes());
+            code.push_str("\n//# sourceMappingURL=data:application/json;base64,");
+            code.push_str(&data_url);
+        }
+        None
+    } else {
+        map
+    };
+
+    let errors = if parser_ret.errors.is_empty() {
+        vec![]
+    } else {
+        let source = Arc::new(NamedSource::new(filename, source_text));
+        parser_ret
+            .errors
+            .into_iter()
+            .map(|diagnostic| diagnostic.with_source_code(Arc::clone(&source)))
+            .map(|error| format!("{error:?}"))
+            .collect()
+    };
+
+    TransformResult { code, map, errors }
+}